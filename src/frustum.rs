@@ -0,0 +1,109 @@
+// "ami" crate - Licensed under the MIT LICENSE
+//  * Copyright (c) 2017  Douglas P. Lau
+//  * Copyright (c) 2017-2018  Jeron A. Lau <jeron.lau@plopgrizzly.com>
+
+use Vec3;
+use BCube;
+use BBox;
+
+/// A half-space, used as one face of a `Frustum`.
+///
+/// A point `p` is inside the half-space when `normal · p + offset >= 0`.
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+	pub normal: Vec3,
+	pub offset: f32,
+}
+
+impl Plane {
+	/// Create a new `Plane` from a normal and offset.
+	pub fn new(normal: Vec3, offset: f32) -> Plane {
+		Plane { normal, offset }
+	}
+
+	/// Signed distance from `p` to this plane; negative is outside.
+	fn distance(&self, p: Vec3) -> f32 {
+		self.normal.x * p.x + self.normal.y * p.y + self.normal.z * p.z
+			+ self.offset
+	}
+
+	/// The AABB corner farthest along this plane's normal, chosen
+	/// componentwise from `min`/`max` (the "positive vertex").
+	fn positive_vertex(&self, min: Vec3, max: Vec3) -> Vec3 {
+		Vec3::new(
+			if self.normal.x >= 0.0 { max.x } else { min.x },
+			if self.normal.y >= 0.0 { max.y } else { min.y },
+			if self.normal.z >= 0.0 { max.z } else { min.z },
+		)
+	}
+
+	/// The AABB corner nearest along this plane's normal (the "negative
+	/// vertex"), the opposite corner to `positive_vertex`.
+	fn negative_vertex(&self, min: Vec3, max: Vec3) -> Vec3 {
+		Vec3::new(
+			if self.normal.x >= 0.0 { min.x } else { max.x },
+			if self.normal.y >= 0.0 { min.y } else { max.y },
+			if self.normal.z >= 0.0 { min.z } else { max.z },
+		)
+	}
+}
+
+/// The result of testing an AABB against a `Frustum`.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum FrustumTest {
+	/// The box is entirely outside at least one plane.
+	Outside,
+	/// The box is entirely inside every plane.
+	Inside,
+	/// The box straddles at least one plane.
+	Intersecting,
+}
+
+/// A view frustum, represented as 6 half-spaces (left, right, bottom, top,
+/// near, far) whose intersection is the visible region.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+	pub planes: [Plane; 6],
+}
+
+impl Frustum {
+	/// Create a new `Frustum` from its 6 bounding planes.
+	pub fn new(planes: [Plane; 6]) -> Frustum {
+		Frustum { planes }
+	}
+
+	/// Test an axis-aligned box (given as a min/max corner pair) against
+	/// every plane, using the positive/negative vertex method.
+	fn test(&self, min: Vec3, max: Vec3) -> FrustumTest {
+		let mut intersecting = false;
+
+		for plane in &self.planes {
+			let pos = plane.positive_vertex(min, max);
+			if plane.distance(pos) < 0.0 {
+				return FrustumTest::Outside;
+			}
+
+			let neg = plane.negative_vertex(min, max);
+			if plane.distance(neg) < 0.0 {
+				intersecting = true;
+			}
+		}
+
+		if intersecting {
+			FrustumTest::Intersecting
+		} else {
+			FrustumTest::Inside
+		}
+	}
+
+	/// Test a `BCube` against this `Frustum`.
+	pub fn test_bcube(&self, bcube: BCube) -> FrustumTest {
+		let (max, min) = bcube.to_point_pair();
+		self.test(min, max)
+	}
+
+	/// Test a `BBox` against this `Frustum`.
+	pub fn test_bbox(&self, bbox: BBox) -> FrustumTest {
+		self.test(bbox.min, bbox.max)
+	}
+}
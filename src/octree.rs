@@ -2,6 +2,7 @@
 //  * Copyright (c) 2017  Douglas P. Lau
 //  * Copyright (c) 2017-2018  Jeron A. Lau <jeron.lau@plopgrizzly.com>
 
+use std::alloc::Layout;
 use std::fmt;
 // use std::cmp::Ordering;
 
@@ -9,7 +10,8 @@ use Vec3;
 use BCube;
 use BBox;
 use Collider;
-// use Frustum;
+use Frustum;
+use FrustumTest;
 
 /// An octree is a DAG that can quickly search for points in 3D space.
 ///
@@ -21,6 +23,21 @@ use Collider;
 /// The nodes are stored in a vector, and are indexed using a 32-bit node ID.
 /// This saves memory over using pointers on 64-bit systems.  Node ID 1 is the
 /// first node in the vector.
+///
+/// ## No lock-free concurrent readers
+///
+/// A copy-on-write snapshot API (`read`/`write` transactions, à la
+/// concread's cursors) was attempted for this layout and reverted: with
+/// nodes kept in one `Vec<Node>` indexed by position, a read snapshot and a
+/// write transaction both need to borrow `Octree` itself (`&'a`/`&'a mut`),
+/// which the borrow checker will never allow to coexist — the API would be
+/// unusable for its stated purpose of a render thread reading while a sim
+/// thread writes. Doing this for real would mean storing `nodes` (and
+/// `colliders`) behind `Arc`, path-copying only the touched root-to-leaf
+/// spine per write, and handing read txns an owned `Arc` clone of the root
+/// instead of a borrow — a change to the storage representation, not an
+/// addition on top of it. Out of scope here; left unimplemented rather
+/// than shipped half-working.
 pub struct Octree<T: Collider> {
 	colliders: Vec<T>,
 	collider_garbage: Vec<Id>,
@@ -34,6 +51,53 @@ pub struct Octree<T: Collider> {
 const LINK: usize = 15;			// link to coincident leaf nodes
 const LEAF: u32 = 0xFF_FF_FF_FF;	// max u32 value (invalid handle)
 
+/// The error returned by `try_add` when growing the octree's backing
+/// storage would exceed available memory.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CollectionAllocErr {
+	/// The requested capacity overflowed `usize`, or would overflow
+	/// `isize::MAX` bytes; no allocation was attempted.
+	CapacityOverflow,
+	/// The allocator couldn't satisfy a request to grow the storage.
+	AllocErr {
+		/// The layout of the storage grown by exactly one element.  `Vec`
+		/// grows its capacity in larger steps, so the request that failed
+		/// may have been for more than this.
+		layout: Layout,
+	},
+}
+
+impl fmt::Display for CollectionAllocErr {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CollectionAllocErr::CapacityOverflow =>
+				write!(f, "capacity overflow"),
+			CollectionAllocErr::AllocErr { layout } =>
+				write!(f, "memory allocation of {} bytes failed",
+					layout.size()),
+		}
+	}
+}
+
+/// Reserve room for one more element, without pushing it, reporting
+/// failure instead of aborting the process.
+fn try_reserve_one<U>(v: &mut Vec<U>) -> Result<(), CollectionAllocErr> {
+	if v.len() == v.capacity() {
+		if v.try_reserve(1).is_err() {
+			// `Vec::try_reserve`'s error doesn't expose the layout it
+			// tried on stable, so compute the layout for one more
+			// element instead: if even that isn't representable, report
+			// a capacity overflow; otherwise report an allocation
+			// failure carrying that layout.
+			return Err(match Layout::array::<U>(v.len() + 1) {
+				Ok(layout) => CollectionAllocErr::AllocErr { layout },
+				Err(_) => CollectionAllocErr::CapacityOverflow,
+			});
+		}
+	}
+	Ok(())
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct Id(u32);
 
@@ -181,15 +245,14 @@ impl Node {
 		}
 	}
 
-	/// Find the first open child slot in a branch, None if full.
+	/// Find the first open child slot in a branch, None if full.  The
+	/// returned index is an absolute index into `child` (8..=14), not a
+	/// position relative to that range.
 	fn branch_open_slot(&self) -> Option<usize> {
 		assert!(self.is_branch());
 		// Skip 0-7 as that is descending the octree, and skip 15 (link)
 		let slot = self.child[8..=14].iter().position(|v| v.is_none());
-		if let Some(s) = slot {
-			return Some(s); // same as slot
-		}
-		None
+		slot.map(|s| s + 8)
 	}
 
 	/// Add a collider to a branch node.
@@ -254,14 +317,29 @@ impl Node {
 		}
 	}
 
-	/// Determine which child for a branch bbox, if there is one it fully
-	/// fits into.
-	fn which_child_bbox(c: Vec3, p: BBox) -> Option<usize> {
-		let min = Self::which_child(c, p.min);
-		let max = Self::which_child(c, p.max);
+	/// Determine which child of `bcube` the bbox `p` fully fits into, if
+	/// any.  A sign test against the center alone isn't enough: a bbox can
+	/// straddle the center of a child it was signed into (it's larger than
+	/// that child), so the child's actual bounds must contain `p` too.
+	fn which_child_bbox(bcube: BCube, p: BBox) -> Option<usize> {
+		let min = Self::which_child(bcube.center, p.min);
+		let max = Self::which_child(bcube.center, p.max);
+
+		if min != max {
+			return None;
+		}
+
+		let ch = min;
+		let cb = Node::child_bcube(ch, bcube);
+		let lo = Vec3::new(cb.center.x - cb.half_len, cb.center.y - cb.half_len,
+			cb.center.z - cb.half_len);
+		let hi = Vec3::new(cb.center.x + cb.half_len, cb.center.y + cb.half_len,
+			cb.center.z + cb.half_len);
 
-		if min == max {
-			Some(min)
+		if p.min.x >= lo.x && p.min.y >= lo.y && p.min.z >= lo.z
+			&& p.max.x <= hi.x && p.max.y <= hi.y && p.max.z <= hi.z
+		{
+			Some(ch)
 		} else {
 			None
 		}
@@ -315,10 +393,18 @@ impl<T> Octree<T> where T: Collider {
 		}
 	}
 
-	/// Add a point in the octree
+	/// Add a point in the octree, aborting the process on allocation
+	/// failure.  See `try_add` for a fallible version.
 	pub fn add(&mut self, point: T) -> Id {
+		self.try_add(point).expect("out of memory")
+	}
+
+	/// Add a point in the octree, reporting allocation failure instead of
+	/// aborting.
+	pub fn try_add(&mut self, point: T) -> Result<Id, CollectionAllocErr> {
 		// Add to colliders and get the id.
-		let id = if let Some(id) = self.collider_garbage.pop() {
+		let reused = self.collider_garbage.pop();
+		let id = if let Some(id) = reused {
 			unsafe {
 				::std::ptr::copy_nonoverlapping(&point,
 					&mut self.colliders[{ let id: usize = id.into(); id }], 1);
@@ -326,23 +412,36 @@ impl<T> Octree<T> where T: Collider {
 			::std::mem::forget(point); // don't drop it, it's moved!
 			id
 		} else {
+			try_reserve_one(&mut self.colliders)?;
 			self.colliders.push(point);
 			Id(self.colliders.len() as u32)
 		};
 
 		// Find position in octree for this new collider.
-		match self.n_colliders {
-			0 => self.add_0(id),
-			_ => self.add_n(id),
+		let inserted = match self.n_colliders {
+			0 => self.try_add_0(id),
+			_ => self.try_add_n(id),
+		};
+
+		if let Err(e) = inserted {
+			// The collider slot above was claimed before we knew the
+			// tree insertion would succeed; give it back so a failed
+			// try_add doesn't permanently strand it.
+			if reused.is_some() {
+				self.collider_garbage.push(id);
+			} else {
+				self.colliders.pop();
+			}
+			return Err(e);
 		}
 
 		// Increment number of colliders, and return id
 		self.n_colliders += 1;
-		id
+		Ok(id)
 	}
 
 	/// Add a point when empty
-	fn add_0(&mut self, id: Id) {
+	fn try_add_0(&mut self, id: Id) -> Result<(), CollectionAllocErr> {
 		// Number of colliders must be 0
 		assert!(self.n_colliders == 0);
 
@@ -354,15 +453,16 @@ impl<T> Octree<T> where T: Collider {
 		self.bcube = self[id].bbox().into();
 
 		// Build the branch and add a collider.
-		let i = self.new_branch();
+		let i = self.try_new_branch()?;
 		self.nodes[{ let i: usize = i.into(); i }].branch_add_collider(id).unwrap();
 
 		// Set this branch as the root node.
 		self.root = i;
+		Ok(())
 	}
 
 	/// Add a point when not empty
-	fn add_n(&mut self, id: Id) {
+	fn try_add_n(&mut self, id: Id) -> Result<(), CollectionAllocErr> {
 		// Must have colliders already in the octree.
 		assert!(self.n_colliders > 0);
 		// Get BBox
@@ -372,15 +472,16 @@ impl<T> Octree<T> where T: Collider {
 
 		// While the bbox isn't within the root bcube, expand root bcube
 		while !bbox.collide_bcube(bcube) {
-			self.grow_root(bbox);
+			self.try_grow_root(bbox)?;
 		}
 
 		// Add id inside the root bcube.
-		self.add_inside(id, root, bcube);
+		self.try_add_inside(id, root, bcube)?;
+		Ok(())
 	}
 
 	/// Grow the root node
-	fn grow_root(&mut self, bbox: BBox) {
+	fn try_grow_root(&mut self, bbox: BBox) -> Result<(), CollectionAllocErr> {
 		// BBox can't collide with bcube when this function is called.
 		assert!(!bbox.collide_bcube(self.bcube));
 		assert!(self.nodes[{ let a: usize = self.root.into(); a }].is_branch());
@@ -394,13 +495,16 @@ impl<T> Octree<T> where T: Collider {
 
 		// Create new container branch for old root branch.
 		let ch = Node::which_child(self.bcube.center, center);
-		let id = self.new_branch();
+		let id = self.try_new_branch()?;
 		self.nodes[{ let a: usize = id.into(); a }].child[ch] = self.root;
 		self.root = id;
+		Ok(())
 	}
 
 	/// Add a point within the bounds
-	fn add_inside(&mut self, id: Id, node_id: Id, bcube: BCube) {
+	fn try_add_inside(&mut self, id: Id, node_id: Id, bcube: BCube)
+		-> Result<(), CollectionAllocErr>
+	{
 		// Calculate bbox for this id.
 		let bbox = self[id].bbox();
 		// Convert node_id to usize for indexing.
@@ -416,7 +520,7 @@ impl<T> Octree<T> where T: Collider {
 			// Attempt to push relative root colliders down the tree
 			for i in 8..=14 {
 				let collider = self.nodes[node_id].child[i];
-				if self.add_down(collider, node_id, bcube) {
+				if self.try_add_down(collider, node_id, bcube)? {
 					// If it successfully pushed it the
 					// collider down the octree, remove it
 					// from it's old location.
@@ -426,37 +530,41 @@ impl<T> Octree<T> where T: Collider {
 			}
 
 			// Attempt to push this collider (id) down the tree
-			if self.add_down(id, node_id, bcube) {
-				return;
+			if self.try_add_down(id, node_id, bcube)? {
+				return Ok(());
 			}
 
 			// Try again, this time link if failed.
 			if self.nodes[node_id].branch_add_collider(id)
 				.is_none() // Is full, still!
 			{
-				let link_id = self.new_leaf();
+				let link_id = self.try_new_leaf()?;
 				self.nodes[node_id].child[LINK]
 					= link_id;
 			}
 		}
+
+		Ok(())
 	}
 
 	/// Move a collider down the tree, return true if it worked.
-	fn add_down(&mut self, id: Id, node_id: usize, bcube: BCube) -> bool {
+	fn try_add_down(&mut self, id: Id, node_id: usize, bcube: BCube)
+		-> Result<bool, CollectionAllocErr>
+	{
 		// Calculate bbox for this id.
 		let bbox = self[id].bbox();
 
 		// can be put on a lower level.
-		if let Some(ch) = Node::which_child_bbox(bcube.center, bbox) {
+		if let Some(ch) = Node::which_child_bbox(bcube, bbox) {
 			let j = self.nodes[node_id].child[ch];
 			let bc = Node::child_bcube(ch, bcube);
 
 			if j.is_some() {
 				// already a branch here, add collider to it.
-				self.add_inside(id, j, bc);
+				self.try_add_inside(id, j, bc)?;
 			} else {
 				// make a branch
-				let k = self.new_branch();
+				let k = self.try_new_branch()?;
 				// set branch as the correct child
 				self.nodes[node_id].child[ch] = k;
 				// Add the collider
@@ -464,55 +572,62 @@ impl<T> Octree<T> where T: Collider {
 					.branch_add_collider(id)
 					.unwrap(); // shouldn't fail.
 			}
-			true
+			Ok(true)
 		} else {
-			false
+			Ok(false)
 		}
 	}
 
-	/// Add a new node
-	fn new_node(&mut self, n: Node) -> Id {
+	/// Add a new node, reporting allocation failure instead of aborting.
+	fn try_new_node(&mut self, n: Node) -> Result<Id, CollectionAllocErr> {
 		if let Some(i) = self.garbage.pop() {
 			let k: usize = i.into();
 			self.nodes[k] = n;
-			k.into()
+			Ok(k.into())
 		} else {
+			try_reserve_one(&mut self.nodes)?;
 			self.nodes.push(n);
-			Id(self.nodes.len() as u32)
+			Ok(Id(self.nodes.len() as u32))
 		}
 	}
 
 	/// Add a new leaf node
-	fn new_leaf(&mut self) -> Id {
-		self.new_node(Node::new_leaf())
+	fn try_new_leaf(&mut self) -> Result<Id, CollectionAllocErr> {
+		self.try_new_node(Node::new_leaf())
 	}
 
 	/// Add a new branch node
-	fn new_branch(&mut self) -> Id {
-		self.new_node(Node::new_branch())
+	fn try_new_branch(&mut self) -> Result<Id, CollectionAllocErr> {
+		self.try_new_node(Node::new_branch())
 	}
 
 	/// Remove a point from the octree
 	pub fn remove(&mut self, id: Id) -> T {
 		// Must have colliders already in the octree.
 		assert!(self.n_colliders > 0);
-		// 
+		//
 		let bcube = self.bcube;
 		let root = self.root;
 		// Find and remove the collider Id from the octree.
-		// Should always be None TODO: maybe if len() is 1, octree should be emptied and actually returns None
-		assert_eq!(self.remove_inside(id, root, bcube), None);
-		// For indexing.
-		let root: usize = self.root.into();
+		if let Some(rm) = self.remove_inside(id, root, bcube) {
+			// The root branch held this collider directly and is now
+			// empty itself; there's no parent to repoint, so retire it.
+			assert_eq!(rm, root);
+			self.garbage.push(root);
+			self.root = Id::none();
+		} else {
+			// For indexing.
+			let root: usize = self.root.into();
+			// Shrink root if: 1 branch, no nodes
+			if let Some(ch) = self.nodes[root].branch_is_one() {
+				// Add root to garbage.
+				self.garbage.push(self.root);
+				// Set new root
+				self.root = self.nodes[root].child[ch];
+			}
+		}
 		// Id is garbage now.
 		self.collider_garbage.push(id);
-		// Shrink root if: 1 branch, no nodes
-		if let Some(ch) = self.nodes[root].branch_is_one() {
-			// Add root to garbage.
-			self.garbage.push(self.root);
-			// Set new root
-			self.root = self.nodes[root].child[ch];
-		}
 		// Decrement number of colliders
 		self.n_colliders -= 1;
 
@@ -540,7 +655,7 @@ impl<T> Octree<T> where T: Collider {
 		assert!(self.nodes[node_id].is_branch());
 
 		// Could be found on a lower level.
-		if let Some(ch) = Node::which_child_bbox(bcube.center, bbox) {
+		if let Some(ch) = Node::which_child_bbox(bcube, bbox) {
 			let j = self.nodes[node_id].child[ch];
 
 			if j.is_some() {
@@ -600,6 +715,424 @@ impl<T> Octree<T> where T: Collider {
 
 		None // Don't remove this node
 	}
+
+	/// Cast a ray and find the nearest collider it intersects.
+	///
+	/// Returns the hit collider's `Id`, the point of intersection, and the
+	/// surface normal at that point.
+	pub fn cast_ray(&self, origin: Vec3, dir: Vec3) -> Option<(Id, Vec3, Vec3)> {
+		if self.root.is_none() {
+			return None;
+		}
+
+		let mut best: Option<(Id, f32, Vec3, Vec3)> = None;
+		self.cast_ray_node(self.root, self.bcube, origin, dir, &mut best);
+		best.map(|(id, _, point, normal)| (id, point, normal))
+	}
+
+	/// Recursive descent helper for `cast_ray`.
+	fn cast_ray_node(&self, node_id: Id, bcube: BCube, origin: Vec3, dir: Vec3,
+		best: &mut Option<(Id, f32, Vec3, Vec3)>)
+	{
+		let idx: usize = node_id.into();
+		let node = &self.nodes[idx];
+
+		// Test colliders stored directly in this node.
+		let slots = if node.is_branch() { 8..=14 } else { 1..=14 };
+		for i in slots {
+			let id = node.child[i];
+			if id.is_some() {
+				let bbox = self[id].bbox();
+				if let Some((t, point, normal)) = ray_bbox_hit(origin, dir, bbox) {
+					if best.map_or(true, |(_, bt, _, _)| t < bt) {
+						*best = Some((id, t, point, normal));
+					}
+				}
+			}
+		}
+
+		// Follow the link to a coincident leaf, if any.
+		if let Some(link) = node.link() {
+			self.cast_ray_node(link.into(), bcube, origin, dir, best);
+		}
+
+		if node.is_leaf() {
+			return;
+		}
+
+		// Visit child branches in order of increasing slab entry distance,
+		// so we can stop as soon as a confirmed hit beats the next child.
+		let mut children: Vec<(usize, f32)> = Vec::new();
+		for ch in 0..8 {
+			let id = node.child[ch];
+			if id.is_some() {
+				let cb = Node::child_bcube(ch, bcube);
+				let (max, min) = cb.to_point_pair();
+				if let Some((tmin, _)) = ray_aabb(origin, dir, min, max) {
+					children.push((ch, tmin));
+				}
+			}
+		}
+		children.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+		for (ch, tmin) in children {
+			if let Some((_, bt, _, _)) = *best {
+				if bt < tmin {
+					break; // every remaining child starts farther away
+				}
+			}
+			let id = node.child[ch];
+			let cb = Node::child_bcube(ch, bcube);
+			self.cast_ray_node(id, cb, origin, dir, best);
+		}
+	}
+
+	/// Collect the `Id` of every collider whose `bbox()` overlaps `region`.
+	pub fn query_bbox(&self, region: BBox) -> Vec<Id> {
+		let mut out = Vec::new();
+		if self.root.is_some() {
+			self.query_bbox_node(self.root, self.bcube, region, &mut out);
+		}
+		out
+	}
+
+	/// Recursive descent helper for `query_bbox`.
+	fn query_bbox_node(&self, node_id: Id, bcube: BCube, region: BBox, out: &mut Vec<Id>) {
+		let idx: usize = node_id.into();
+		let node = &self.nodes[idx];
+
+		let slots = if node.is_branch() { 8..=14 } else { 1..=14 };
+		for i in slots {
+			let id = node.child[i];
+			if id.is_some() && region.collide(self[id].bbox()) {
+				out.push(id);
+			}
+		}
+
+		if let Some(link) = node.link() {
+			self.query_bbox_node(link.into(), bcube, region, out);
+		}
+
+		if node.is_leaf() {
+			return;
+		}
+
+		for ch in 0..8 {
+			let id = node.child[ch];
+			if id.is_some() {
+				let cb = Node::child_bcube(ch, bcube);
+				if region.collide_bcube(cb) {
+					self.query_bbox_node(id, cb, region, out);
+				}
+			}
+		}
+	}
+
+	/// Collect the `Id` of every collider visible within `f`, culling
+	/// subtrees whose bounds fall entirely outside the frustum.
+	pub fn query_frustum(&self, f: &Frustum) -> Vec<Id> {
+		let mut out = Vec::new();
+		if self.root.is_some() {
+			self.query_frustum_node(self.root, self.bcube, f, &mut out);
+		}
+		out
+	}
+
+	/// Recursive descent helper for `query_frustum`.
+	fn query_frustum_node(&self, node_id: Id, bcube: BCube, f: &Frustum,
+		out: &mut Vec<Id>)
+	{
+		match f.test_bcube(bcube) {
+			FrustumTest::Outside => return,
+			FrustumTest::Inside => {
+				self.collect_ids_node(node_id, out);
+				return;
+			}
+			FrustumTest::Intersecting => {}
+		}
+
+		let idx: usize = node_id.into();
+		let node = &self.nodes[idx];
+
+		let slots = if node.is_branch() { 8..=14 } else { 1..=14 };
+		for i in slots {
+			let id = node.child[i];
+			if id.is_some() && f.test_bbox(self[id].bbox()) != FrustumTest::Outside {
+				out.push(id);
+			}
+		}
+
+		if let Some(link) = node.link() {
+			self.query_frustum_node(link.into(), bcube, f, out);
+		}
+
+		if node.is_leaf() {
+			return;
+		}
+
+		for ch in 0..8 {
+			let id = node.child[ch];
+			if id.is_some() {
+				let cb = Node::child_bcube(ch, bcube);
+				self.query_frustum_node(id, cb, f, out);
+			}
+		}
+	}
+
+	/// Find the `Id` of the collider nearest to `point`.
+	pub fn nearest(&self, point: Vec3) -> Option<Id> {
+		if self.root.is_none() {
+			return None;
+		}
+
+		let mut best: Option<(Id, f32)> = None;
+		self.nearest_node(self.root, self.bcube, point, &mut best);
+		best.map(|(id, _)| id)
+	}
+
+	/// Best-first branch-and-bound descent helper for `nearest`.
+	fn nearest_node(&self, node_id: Id, bcube: BCube, point: Vec3,
+		best: &mut Option<(Id, f32)>)
+	{
+		let idx: usize = node_id.into();
+		let node = &self.nodes[idx];
+
+		let slots = if node.is_branch() { 8..=14 } else { 1..=14 };
+		for i in slots {
+			let id = node.child[i];
+			if id.is_some() {
+				let d = bbox_dist_sq(point, self[id].bbox());
+				if best.map_or(true, |(_, bd)| d < bd) {
+					*best = Some((id, d));
+				}
+			}
+		}
+
+		if let Some(link) = node.link() {
+			self.nearest_node(link.into(), bcube, point, best);
+		}
+
+		if node.is_leaf() {
+			return;
+		}
+
+		// Visit children nearest-first, pruning any whose closest possible
+		// point is already farther than the current best match.
+		let mut children: Vec<(usize, BCube, f32)> = Vec::new();
+		for ch in 0..8 {
+			let id = node.child[ch];
+			if id.is_some() {
+				let cb = Node::child_bcube(ch, bcube);
+				children.push((ch, cb, bcube_dist_sq(point, cb)));
+			}
+		}
+		children.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+		for (ch, cb, d) in children {
+			if let Some((_, bd)) = *best {
+				if d > bd {
+					break;
+				}
+			}
+			let id = node.child[ch];
+			self.nearest_node(id, cb, point, best);
+		}
+	}
+
+	/// Get an iterator over every live collider's `Id` and value.
+	pub fn iter(&self) -> Iter<T> {
+		Iter { tree: self, ids: self.collect_ids().into_iter() }
+	}
+
+	/// Visit every live collider with `f`.
+	pub fn visit<F: FnMut(Id, &T)>(&self, mut f: F) {
+		for id in self.collect_ids() {
+			f(id, &self[id]);
+		}
+	}
+
+	/// Fold every collider's `bbox()` into a single, tight `BBox`, or
+	/// `None` if the octree is empty.
+	pub fn bounds(&self) -> Option<BBox> {
+		let mut iter = self.iter();
+		let first = iter.next()?.1.bbox();
+		let mut min = first.min;
+		let mut max = first.max;
+
+		for (_, collider) in iter {
+			let bbox = collider.bbox();
+			min.x = min.x.min(bbox.min.x);
+			min.y = min.y.min(bbox.min.y);
+			min.z = min.z.min(bbox.min.z);
+			max.x = max.x.max(bbox.max.x);
+			max.y = max.y.max(bbox.max.y);
+			max.z = max.z.max(bbox.max.z);
+		}
+
+		Some(BBox::new(min, max))
+	}
+
+	/// Collect the `Id` of every live collider, following branch slots,
+	/// leaf slots, and link chains from `root`.
+	fn collect_ids(&self) -> Vec<Id> {
+		let mut out = Vec::new();
+		if self.root.is_some() {
+			self.collect_ids_node(self.root, &mut out);
+		}
+		out
+	}
+
+	/// Recursive descent helper for `collect_ids`.
+	fn collect_ids_node(&self, node_id: Id, out: &mut Vec<Id>) {
+		let idx: usize = node_id.into();
+		let node = &self.nodes[idx];
+
+		let slots = if node.is_branch() { 8..=14 } else { 1..=14 };
+		for i in slots {
+			let id = node.child[i];
+			if id.is_some() {
+				out.push(id);
+			}
+		}
+
+		if let Some(link) = node.link() {
+			self.collect_ids_node(link.into(), out);
+		}
+
+		if node.is_branch() {
+			for ch in 0..8 {
+				let id = node.child[ch];
+				if id.is_some() {
+					self.collect_ids_node(id, out);
+				}
+			}
+		}
+	}
+}
+
+/// Iterator over every live collider's `Id` and value, from `Octree::iter`.
+pub struct Iter<'a, T: 'a + Collider> {
+	tree: &'a Octree<T>,
+	ids: ::std::vec::IntoIter<Id>,
+}
+
+impl<'a, T: Collider> Iterator for Iter<'a, T> {
+	type Item = (Id, &'a T);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let id = self.ids.next()?;
+		Some((id, &self.tree[id]))
+	}
+}
+
+impl<'a, T: Collider> IntoIterator for &'a Octree<T> {
+	type Item = (Id, &'a T);
+	type IntoIter = Iter<'a, T>;
+
+	fn into_iter(self) -> Iter<'a, T> {
+		self.iter()
+	}
+}
+
+/// Clamp `v` to the range `[lo, hi]`.
+fn clamp(v: f32, lo: f32, hi: f32) -> f32 {
+	if v < lo { lo } else if v > hi { hi } else { v }
+}
+
+/// Squared distance from `p` to the nearest point within `bbox`.
+fn bbox_dist_sq(p: Vec3, bbox: BBox) -> f32 {
+	let dx = p.x - clamp(p.x, bbox.min.x, bbox.max.x);
+	let dy = p.y - clamp(p.y, bbox.min.y, bbox.max.y);
+	let dz = p.z - clamp(p.z, bbox.min.z, bbox.max.z);
+	dx * dx + dy * dy + dz * dz
+}
+
+/// Squared distance from `p` to the nearest point within `bcube`.
+fn bcube_dist_sq(p: Vec3, bcube: BCube) -> f32 {
+	let h = bcube.half_len;
+	let c = bcube.center;
+	let dx = p.x - clamp(p.x, c.x - h, c.x + h);
+	let dy = p.y - clamp(p.y, c.y - h, c.y + h);
+	let dz = p.z - clamp(p.z, c.z - h, c.z + h);
+	dx * dx + dy * dy + dz * dz
+}
+
+/// Intersect a ray with an axis-aligned slab region, using the slab method.
+/// Returns `(tmin, tmax)` along the ray, or `None` if the ray misses.
+fn ray_aabb(origin: Vec3, dir: Vec3, min: Vec3, max: Vec3) -> Option<(f32, f32)> {
+	let mut tmin = ::std::f32::NEG_INFINITY;
+	let mut tmax = ::std::f32::INFINITY;
+
+	for axis in 0..3 {
+		let (o, d, lo, hi) = match axis {
+			0 => (origin.x, dir.x, min.x, max.x),
+			1 => (origin.y, dir.y, min.y, max.y),
+			_ => (origin.z, dir.z, min.z, max.z),
+		};
+
+		if d.abs() < ::std::f32::EPSILON {
+			// Ray is parallel to this axis; miss unless already inside it.
+			if o < lo || o > hi {
+				return None;
+			}
+		} else {
+			let mut t1 = (lo - o) / d;
+			let mut t2 = (hi - o) / d;
+			if t1 > t2 {
+				::std::mem::swap(&mut t1, &mut t2);
+			}
+			tmin = tmin.max(t1);
+			tmax = tmax.min(t2);
+			if tmax < tmin.max(0.0) {
+				return None;
+			}
+		}
+	}
+
+	Some((tmin, tmax))
+}
+
+/// Intersect a ray with a `BBox`, returning the nearest positive `t` along
+/// with the hit point and surface normal.
+fn ray_bbox_hit(origin: Vec3, dir: Vec3, bbox: BBox) -> Option<(f32, Vec3, Vec3)> {
+	let (tmax_pt, tmin_pt) = (bbox.max, bbox.min);
+	let (tmin, tmax) = ray_aabb(origin, dir, tmin_pt, tmax_pt)?;
+
+	let t = if tmin >= 0.0 {
+		tmin
+	} else if tmax >= 0.0 {
+		tmax
+	} else {
+		return None;
+	};
+
+	let point = Vec3::new(
+		origin.x + dir.x * t,
+		origin.y + dir.y * t,
+		origin.z + dir.z * t,
+	);
+	let normal = bbox_normal_at(bbox, point);
+
+	Some((t, point, normal))
+}
+
+/// Find the outward surface normal of a `BBox` at a point on its surface.
+fn bbox_normal_at(bbox: BBox, point: Vec3) -> Vec3 {
+	let eps = 0.0001;
+
+	if (point.x - bbox.min.x).abs() < eps {
+		Vec3::new(-1.0, 0.0, 0.0)
+	} else if (point.x - bbox.max.x).abs() < eps {
+		Vec3::new(1.0, 0.0, 0.0)
+	} else if (point.y - bbox.min.y).abs() < eps {
+		Vec3::new(0.0, -1.0, 0.0)
+	} else if (point.y - bbox.max.y).abs() < eps {
+		Vec3::new(0.0, 1.0, 0.0)
+	} else if (point.z - bbox.min.z).abs() < eps {
+		Vec3::new(0.0, 0.0, -1.0)
+	} else {
+		Vec3::new(0.0, 0.0, 1.0)
+	}
 }
 
 impl<T> ::std::ops::Index<Id> for Octree<T> where T: Collider {
@@ -617,3 +1150,156 @@ impl<T> ::std::ops::IndexMut<Id> for Octree<T> where T: Collider {
 		&mut self.colliders[index]
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use Plane;
+
+	struct Point(Vec3);
+
+	impl Collider for Point {
+		fn bbox(&self) -> BBox {
+			BBox::new(self.0, self.0)
+		}
+	}
+
+	struct Cube(BBox);
+
+	impl Collider for Cube {
+		fn bbox(&self) -> BBox {
+			self.0
+		}
+	}
+
+	#[test]
+	fn cast_ray_hits_the_nearer_of_two_colliders() {
+		let mut tree: Octree<Cube> = Octree::new();
+		let near = tree.add(Cube(BBox::new(
+			Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0))));
+		let _far = tree.add(Cube(BBox::new(
+			Vec3::new(-1.0, -1.0, 9.0), Vec3::new(1.0, 1.0, 11.0))));
+
+		let hit = tree.cast_ray(Vec3::new(0.0, 0.0, -10.0), Vec3::new(0.0, 0.0, 1.0));
+		assert_eq!(hit.map(|(id, _, _)| id), Some(near));
+	}
+
+	#[test]
+	fn cast_ray_misses_when_nothing_is_in_the_way() {
+		let mut tree: Octree<Cube> = Octree::new();
+		tree.add(Cube(BBox::new(
+			Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0))));
+
+		let hit = tree.cast_ray(Vec3::new(100.0, 100.0, -10.0), Vec3::new(0.0, 0.0, 1.0));
+		assert_eq!(hit, None);
+	}
+
+	#[test]
+	fn nearest_finds_the_point_just_added() {
+		let mut tree: Octree<Point> = Octree::new();
+		let id = tree.add(Point(Vec3::new(1.0, 2.0, 3.0)));
+
+		assert_eq!(tree.nearest(Vec3::new(1.0, 2.0, 3.0)), Some(id));
+	}
+
+	#[test]
+	fn nearest_picks_the_closer_of_two_colliders_sharing_a_branch() {
+		let mut tree: Octree<Point> = Octree::new();
+		let near = tree.add(Point(Vec3::new(1.0, 0.0, 0.0)));
+		let _far = tree.add(Point(Vec3::new(5.0, 0.0, 0.0)));
+
+		assert_eq!(tree.nearest(Vec3::new(0.0, 0.0, 0.0)), Some(near));
+	}
+
+	#[test]
+	fn query_bbox_finds_a_point_inside_the_region() {
+		let mut tree: Octree<Point> = Octree::new();
+		let id = tree.add(Point(Vec3::new(0.0, 0.0, 0.0)));
+		let region = BBox::new(
+			Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+
+		assert_eq!(tree.query_bbox(region), vec![id]);
+	}
+
+	#[test]
+	fn query_bbox_finds_multiple_colliders_sharing_a_branch() {
+		// All 3 land directly in the root branch's collider slots
+		// (8..=14); this is what `branch_add_collider` clobbered before
+		// `branch_open_slot` returned an absolute index into that range.
+		let mut tree: Octree<Point> = Octree::new();
+		let a = tree.add(Point(Vec3::new(0.0, 0.0, 0.0)));
+		let b = tree.add(Point(Vec3::new(0.0, 0.0, 0.0)));
+		let c = tree.add(Point(Vec3::new(0.0, 0.0, 0.0)));
+		let region = BBox::new(
+			Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+
+		let found = tree.query_bbox(region);
+		assert_eq!(found.len(), 3);
+		assert!(found.contains(&a));
+		assert!(found.contains(&b));
+		assert!(found.contains(&c));
+	}
+
+	#[test]
+	fn removed_point_is_no_longer_found() {
+		let mut tree: Octree<Point> = Octree::new();
+		let id = tree.add(Point(Vec3::new(1.0, 2.0, 3.0)));
+
+		tree.remove(id);
+
+		assert_eq!(tree.nearest(Vec3::new(1.0, 2.0, 3.0)), None);
+	}
+
+	#[test]
+	fn visit_reaches_every_collider_and_bounds_covers_them_all() {
+		let mut tree: Octree<Point> = Octree::new();
+		tree.add(Point(Vec3::new(-2.0, 0.0, 0.0)));
+		tree.add(Point(Vec3::new(2.0, 3.0, -4.0)));
+		tree.add(Point(Vec3::new(0.0, -1.0, 1.0)));
+
+		let mut visited = 0;
+		tree.visit(|_, _| visited += 1);
+		assert_eq!(visited, 3);
+
+		let bounds = tree.bounds().unwrap();
+		assert_eq!(bounds.min.x, -2.0);
+		assert_eq!(bounds.min.y, -1.0);
+		assert_eq!(bounds.min.z, -4.0);
+		assert_eq!(bounds.max.x, 2.0);
+		assert_eq!(bounds.max.y, 3.0);
+		assert_eq!(bounds.max.z, 1.0);
+	}
+
+	#[test]
+	fn bounds_is_none_when_empty() {
+		let tree: Octree<Point> = Octree::new();
+		assert!(tree.bounds().is_none());
+	}
+
+	/// An axis-aligned frustum covering `[-h, h]` on every axis.
+	fn box_frustum(h: f32) -> Frustum {
+		Frustum::new([
+			Plane::new(Vec3::new(1.0, 0.0, 0.0), h),
+			Plane::new(Vec3::new(-1.0, 0.0, 0.0), h),
+			Plane::new(Vec3::new(0.0, 1.0, 0.0), h),
+			Plane::new(Vec3::new(0.0, -1.0, 0.0), h),
+			Plane::new(Vec3::new(0.0, 0.0, 1.0), h),
+			Plane::new(Vec3::new(0.0, 0.0, -1.0), h),
+		])
+	}
+
+	#[test]
+	fn query_frustum_finds_points_inside_the_box_and_skips_points_outside() {
+		let mut tree: Octree<Point> = Octree::new();
+		// Two colliders sharing a branch, both inside the frustum.
+		let inside_a = tree.add(Point(Vec3::new(1.0, 1.0, 1.0)));
+		let inside_b = tree.add(Point(Vec3::new(1.0, 1.0, 1.0)));
+		let outside = tree.add(Point(Vec3::new(100.0, 100.0, 100.0)));
+
+		let found = tree.query_frustum(&box_frustum(5.0));
+		assert_eq!(found.len(), 2);
+		assert!(found.contains(&inside_a));
+		assert!(found.contains(&inside_b));
+		assert!(!found.contains(&outside));
+	}
+}